@@ -1,7 +1,7 @@
-use anyhow::{ensure, Context};
+use anyhow::{bail, ensure, Context};
 use async_compatibility_layer::logging::{setup_backtrace, setup_logging};
-use async_std::sync::Arc;
-use clap::{builder::OsStr, Parser};
+use async_std::{sync::Arc, task::sleep};
+use clap::{builder::OsStr, Parser, Subcommand};
 use contract_bindings::{
     erc1967_proxy::ERC1967Proxy,
     hot_shot::HotShot,
@@ -17,32 +17,24 @@ use ethers::{
 };
 use futures::future::{BoxFuture, FutureExt};
 use hotshot_state_prover::service::light_client_genesis;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fs::File,
     io::{stdout, BufReader, Write},
     ops::Deref,
     path::{Path, PathBuf},
+    time::Duration,
 };
 use url::Url;
 
-/// Deploy contracts needed to run the sequencer.
-///
-/// This script deploys contracts needed to run the sequencer to an L1. It outputs a .env file
-/// containing the addresses of the deployed contracts.
-///
-/// This script can also be used to do incremental deployments. The only contract addresses needed
-/// to configure the sequencer network are ESPRESSO_SEQUENCER_HOTSHOT_ADDRESS and
-/// ESPRESSO_SEQUENCER_LIGHT_CLIENT_PROXY_ADDRESS. These contracts, however, have dependencies, and
-/// a full deployment may involve up to 5 total contracts. Some of these contracts, especially
-/// libraries may already have been deployed, or perhaps one of the top-level contracts has been
-/// deployed and we only need to deploy the other one.
+mod verify;
+
+/// Deploy and manage contracts needed to run the sequencer.
 ///
-/// It is possible to pass in the addresses of already deployed contracts, in which case those
-/// addresses will be used in place of deploying a new contract wherever that contract is required
-/// in the deployment process. The generated .env file will include all the addresses passed in as
-/// well as those newly deployed.
+/// This script deploys contracts needed to run the sequencer to an L1, or upgrades an
+/// already-deployed LightClient proxy. See the `deploy` and `upgrade` subcommands for details.
 #[derive(Clone, Debug, Parser)]
 struct Options {
     /// A JSON-RPC endpoint for the L1 to deploy to.
@@ -54,20 +46,10 @@ struct Options {
     )]
     rpc_url: Url,
 
-    /// URL of the HotShot orchestrator.
-    ///
-    /// This is used to get the stake table for initializing the light client contract.
-    #[clap(
-        long,
-        env = "ESPRESSO_SEQUENCER_ORCHESTRATOR_URL",
-        default_value = "http://localhost:40001"
-    )]
-    orchestrator_url: Url,
-
     /// Mnemonic for an L1 wallet.
     ///
-    /// This wallet is used to deploy the contracts, so the account indicated by ACCOUNT_INDEX must
-    /// be funded with with ETH.
+    /// This wallet is used to send transactions, so the account indicated by ACCOUNT_INDEX must be
+    /// funded with with ETH.
     #[clap(
         long,
         name = "MNEMONIC",
@@ -76,7 +58,7 @@ struct Options {
     )]
     mnemonic: String,
 
-    /// Account index in the L1 wallet generated by MNEMONIC to use when deploying the contracts.
+    /// Account index in the L1 wallet generated by MNEMONIC to use when sending transactions.
     #[clap(
         long,
         name = "ACCOUNT_INDEX",
@@ -85,14 +67,310 @@ struct Options {
     )]
     account_index: u32,
 
+    #[clap(subcommand)]
+    command: Command,
+}
+
+/// A subcommand of the deployer script.
+#[derive(Clone, Debug, Subcommand)]
+enum Command {
+    /// Deploy contracts needed to run the sequencer to an L1.
+    ///
+    /// This outputs a .env file containing the addresses of the deployed contracts.
+    ///
+    /// This command can also be used to do incremental deployments. The only contract addresses
+    /// needed to configure the sequencer network are ESPRESSO_SEQUENCER_HOTSHOT_ADDRESS and
+    /// ESPRESSO_SEQUENCER_LIGHT_CLIENT_PROXY_ADDRESS. These contracts, however, have dependencies,
+    /// and a full deployment may involve up to 5 total contracts. Some of these contracts,
+    /// especially libraries may already have been deployed, or perhaps one of the top-level
+    /// contracts has been deployed and we only need to deploy the other one.
+    ///
+    /// It is possible to pass in the addresses of already deployed contracts, in which case those
+    /// addresses will be used in place of deploying a new contract wherever that contract is
+    /// required in the deployment process. The generated .env file will include all the addresses
+    /// passed in as well as those newly deployed.
+    Deploy(DeployOptions),
+
+    /// Upgrade the implementation behind an already-deployed LightClient proxy.
+    ///
+    /// This deploys a fresh LightClient implementation and calls `upgradeToAndCall` on the UUPS
+    /// proxy to point it at the new implementation.
+    Upgrade(UpgradeOptions),
+}
+
+#[derive(Clone, Debug, Parser)]
+struct DeployOptions {
+    /// URL of the HotShot orchestrator.
+    ///
+    /// This is used to get the stake table for initializing the light client contract.
+    #[clap(
+        long,
+        env = "ESPRESSO_SEQUENCER_ORCHESTRATOR_URL",
+        default_value = "http://localhost:40001"
+    )]
+    orchestrator_url: Url,
+
     /// Write deployment results to OUT as a .env file.
     ///
     /// If not provided, the results will be written to stdout.
     #[clap(short, long, name = "OUT", env = "ESPRESSO_DEPLOYER_OUT_PATH")]
     out: Option<PathBuf>,
 
+    /// Simulate the deployment without broadcasting any transactions.
+    ///
+    /// Gas is estimated for each contract and addresses are predicted from the deployer's current
+    /// nonce, so the resulting .env and gas/cost report reflect what a real deployment would do
+    /// without spending ETH or mutating chain state.
+    #[clap(long, env = "ESPRESSO_DEPLOYER_DRY_RUN")]
+    dry_run: bool,
+
+    /// Seconds to wait for the LightClient proxy to emit its initialization event.
+    ///
+    /// After the proxy deploy transaction is confirmed, the deployer watches for the event
+    /// `initialize` is expected to emit, to catch a deployment that mined successfully but
+    /// silently reverted during initialization. If the event is not observed within this many
+    /// seconds, the deployment fails rather than writing out a half-configured proxy address.
+    #[clap(
+        long,
+        env = "ESPRESSO_DEPLOYER_INIT_EVENT_TIMEOUT_SECS",
+        default_value = "120"
+    )]
+    init_event_timeout_secs: u64,
+
     #[clap(flatten)]
     contracts: DeployedContracts,
+
+    #[clap(flatten)]
+    gas: GasOptions,
+
+    #[clap(flatten)]
+    verify: VerifyOptions,
+
+    #[clap(flatten)]
+    broadcast: BroadcastOptions,
+}
+
+/// Options controlling the resumable JSON broadcast artifact.
+#[derive(Clone, Debug, Parser)]
+struct BroadcastOptions {
+    /// Write a JSON broadcast artifact to PATH recording the status of each deployment.
+    ///
+    /// If PATH already exists, any contract recorded there as `confirmed` is treated like a
+    /// predeployed address, so an interrupted deployment can simply be re-run with the same
+    /// `--broadcast-out` to pick up where it left off.
+    #[clap(long, name = "BROADCAST_OUT", env = "ESPRESSO_DEPLOYER_BROADCAST_OUT")]
+    broadcast_out: Option<PathBuf>,
+
+    /// Number of confirmations to wait for before recording a deployment as confirmed.
+    #[clap(long, env = "ESPRESSO_DEPLOYER_CONFIRMATIONS", default_value = "1")]
+    confirmations: usize,
+}
+
+/// Options for post-deployment source verification on an Etherscan-compatible explorer.
+#[derive(Clone, Debug, Parser)]
+struct VerifyOptions {
+    /// Verify deployed contracts' source code after deployment finishes.
+    #[clap(long, env = "ESPRESSO_DEPLOYER_VERIFY")]
+    verify: bool,
+
+    /// API key for the block explorer used to submit verification requests.
+    #[clap(long, env = "ESPRESSO_DEPLOYER_ETHERSCAN_API_KEY")]
+    etherscan_api_key: Option<String>,
+
+    /// Base URL of the verifier API.
+    ///
+    /// Defaults to the Etherscan API; set this to use a Blockscout instance or other
+    /// Etherscan-compatible verifier.
+    #[clap(long, env = "ESPRESSO_DEPLOYER_VERIFIER_URL")]
+    verifier_url: Option<Url>,
+}
+
+#[derive(Clone, Debug, Parser)]
+struct UpgradeOptions {
+    /// Address of the already-deployed LightClient proxy to upgrade.
+    #[clap(long, env = Contract::LightClientProxy)]
+    light_client_proxy: Address,
+
+    /// Use an already-deployed LightClient.sol as the new implementation instead of deploying a
+    /// new one.
+    #[clap(long, env = Contract::LightClient)]
+    light_client: Option<Address>,
+
+    /// Use an already-deployed PlonkVerifier.sol instead of deploying a new one.
+    #[clap(long, env = Contract::PlonkVerifier)]
+    plonk_verifier: Option<Address>,
+
+    /// Use an already-deployed LightClientStateUpdateVK.sol instead of deploying a new one.
+    #[clap(long, env = Contract::StateUpdateVK)]
+    light_client_state_update_vk: Option<Address>,
+
+    /// Calldata to execute against the new implementation via `upgradeToAndCall`, as a migration.
+    ///
+    /// If not given, `upgradeToAndCall` is invoked with empty data, which simply upgrades the
+    /// implementation without calling into it.
+    #[clap(long)]
+    migration_call_data: Option<Bytes>,
+
+    /// Write the new implementation address, along with the addresses of the libraries it is
+    /// linked against, to OUT as a .env file.
+    ///
+    /// If not provided, the results will be written to stdout.
+    #[clap(short, long, name = "OUT", env = "ESPRESSO_DEPLOYER_OUT_PATH")]
+    out: Option<PathBuf>,
+
+    #[clap(flatten)]
+    gas: GasOptions,
+}
+
+/// Gas pricing options for the contract deployment transactions.
+#[derive(Clone, Debug, Parser)]
+struct GasOptions {
+    /// Max fee per gas, in wei, for EIP-1559 deployment transactions.
+    ///
+    /// If not set, this defaults to twice the base fee of the latest block plus the max priority
+    /// fee per gas.
+    #[clap(long, env = "ESPRESSO_DEPLOYER_MAX_FEE_PER_GAS")]
+    max_fee_per_gas: Option<U256>,
+
+    /// Max priority fee per gas, in wei, for EIP-1559 deployment transactions.
+    ///
+    /// If not set, a default is taken from the median priority fee paid in the latest block, via
+    /// `eth_feeHistory`.
+    #[clap(long, env = "ESPRESSO_DEPLOYER_MAX_PRIORITY_FEE_PER_GAS")]
+    max_priority_fee_per_gas: Option<U256>,
+
+    /// Gas price, in wei, for legacy deployment transactions.
+    ///
+    /// Only used with `--legacy`. If not set, a default is fetched from the provider.
+    #[clap(long, env = "ESPRESSO_DEPLOYER_GAS_PRICE")]
+    gas_price: Option<U256>,
+
+    /// Gas limit to use for each deployment transaction.
+    ///
+    /// If not set, the gas limit is estimated automatically.
+    #[clap(long, env = "ESPRESSO_DEPLOYER_GAS_LIMIT")]
+    gas_limit: Option<U256>,
+
+    /// Use legacy (pre-EIP-1559) transactions to deploy contracts.
+    ///
+    /// This is required for chains that do not support the typed transaction envelope.
+    #[clap(long, env = "ESPRESSO_DEPLOYER_LEGACY_TX")]
+    legacy: bool,
+}
+
+impl GasOptions {
+    /// Apply the configured gas pricing to a deployment transaction.
+    ///
+    /// Unless `--legacy` is set, this sets the EIP-1559 fee fields on `tx`. If explicit fees are
+    /// not given, `max_priority_fee_per_gas` is taken from `eth_feeHistory` and
+    /// `max_fee_per_gas` is set to `base_fee * 2 + max_priority_fee_per_gas`, where `base_fee` is
+    /// that of the latest block, mirroring ethers' own default fee estimation.
+    ///
+    /// This mutates the gas fields of `tx` in place, leaving `to`/`data`/`value` (e.g. the
+    /// contract init bytecode or call data `tx` was constructed with) untouched.
+    ///
+    /// Returns the effective per-gas price that was applied, i.e. `gas_price` for a legacy
+    /// transaction or `max_fee_per_gas` for an EIP-1559 one, for use in cost estimates.
+    async fn apply<M>(&self, client: &M, tx: &mut TypedTransaction) -> anyhow::Result<U256>
+    where
+        M: Middleware,
+    {
+        let price = if self.legacy {
+            let gas_price = match self.gas_price {
+                Some(gas_price) => gas_price,
+                None => client.get_gas_price().await.context("fetching gas price")?,
+            };
+            let mut legacy_tx = TransactionRequest::new();
+            legacy_tx.to = tx.to().cloned();
+            legacy_tx.data = tx.data().cloned();
+            legacy_tx.value = tx.value().copied();
+            legacy_tx.from = tx.from().copied();
+            legacy_tx.nonce = tx.nonce().copied();
+            legacy_tx.chain_id = tx.chain_id();
+            legacy_tx.gas_price = Some(gas_price);
+            *tx = legacy_tx.into();
+            gas_price
+        } else {
+            let max_priority_fee_per_gas = match self.max_priority_fee_per_gas {
+                Some(fee) => fee,
+                None => estimate_priority_fee(client).await?,
+            };
+            let max_fee_per_gas = match self.max_fee_per_gas {
+                Some(fee) => fee,
+                None => {
+                    let base_fee = client
+                        .get_block(BlockNumber::Latest)
+                        .await
+                        .context("fetching latest block")?
+                        .context("latest block not found")?
+                        .base_fee_per_gas
+                        .context("L1 does not support EIP-1559 (missing base fee); use --legacy")?;
+                    base_fee * 2 + max_priority_fee_per_gas
+                }
+            };
+            let mut eip1559_tx = Eip1559TransactionRequest::new();
+            eip1559_tx.to = tx.to().cloned();
+            eip1559_tx.data = tx.data().cloned();
+            eip1559_tx.value = tx.value().copied();
+            eip1559_tx.from = tx.from().copied();
+            eip1559_tx.nonce = tx.nonce().copied();
+            eip1559_tx.chain_id = tx.chain_id();
+            eip1559_tx.max_fee_per_gas = Some(max_fee_per_gas);
+            eip1559_tx.max_priority_fee_per_gas = Some(max_priority_fee_per_gas);
+            *tx = eip1559_tx.into();
+            max_fee_per_gas
+        };
+        if let Some(gas_limit) = self.gas_limit {
+            tx.set_gas(gas_limit);
+        }
+        Ok(price)
+    }
+}
+
+/// Estimate a reasonable `max_priority_fee_per_gas` from recent blocks via `eth_feeHistory`.
+async fn estimate_priority_fee<M: Middleware>(client: &M) -> anyhow::Result<U256> {
+    let history = client
+        .fee_history(1u64, BlockNumber::Latest, &[50.0])
+        .await
+        .map_err(|err| anyhow::anyhow!("fetching fee history: {err}"))?;
+    Ok(history
+        .reward
+        .last()
+        .and_then(|rewards| rewards.first())
+        .copied()
+        .unwrap_or_default())
+}
+
+/// Fallback gas estimate used in `--dry-run` when `eth_estimateGas` itself fails.
+///
+/// A predicted (not yet broadcast) dependency has no code at its predicted address, so any
+/// transaction whose execution path touches that address (e.g. the LightClient proxy's
+/// constructor, which delegatecalls its `initialize` calldata into the predicted LightClient
+/// implementation) reverts during estimation even though the real deployment, broadcast in
+/// dependency order, will succeed. Rather than aborting the whole dry-run preview over this,
+/// fall back to a rough fixed estimate so the report can still be printed.
+const DRY_RUN_FALLBACK_GAS: u64 = 3_000_000;
+
+/// Estimate gas for `tx` as part of a `--dry-run` preview, falling back to
+/// [`DRY_RUN_FALLBACK_GAS`] whenever estimation fails for any reason, the expected one being that
+/// `tx` depends on another contract that is only predicted, not actually deployed, yet. This
+/// never returns an error.
+async fn estimate_dry_run_gas<M: Middleware>(
+    client: &M,
+    tx: &TypedTransaction,
+    name: Contract,
+) -> anyhow::Result<U256> {
+    match client.estimate_gas(tx, None).await {
+        Ok(gas) => Ok(gas),
+        Err(err) => {
+            tracing::warn!(
+                "estimating gas for {name} failed ({err}), likely because it depends on a \
+                 contract that is only predicted in this dry run; using a fallback estimate of \
+                 {DRY_RUN_FALLBACK_GAS}"
+            );
+            Ok(U256::from(DRY_RUN_FALLBACK_GAS))
+        }
+    }
 }
 
 /// Set of predeployed contracts.
@@ -120,7 +398,7 @@ struct DeployedContracts {
 }
 
 /// An identifier for a particular contract.
-#[derive(Clone, Copy, Debug, Display, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq, Hash, Serialize, Deserialize)]
 enum Contract {
     #[display(fmt = "ESPRESSO_SEQUENCER_HOTSHOT_ADDRESS")]
     HotShot,
@@ -140,11 +418,162 @@ impl From<Contract> for OsStr {
     }
 }
 
+/// A single entry in a [`DryRunReport`].
+struct DryRunEntry {
+    contract: Contract,
+    address: Address,
+    gas: U256,
+    gas_price: U256,
+}
+
+/// A report of predicted addresses and gas/ETH costs for a simulated (`--dry-run`) deployment.
+#[derive(Default)]
+struct DryRunReport(Vec<DryRunEntry>);
+
+impl DryRunReport {
+    /// Print a per-contract and total gas/ETH cost summary.
+    fn print(&self, mut w: impl Write) -> anyhow::Result<()> {
+        writeln!(w, "Dry run deployment plan:")?;
+        let mut total_gas = U256::zero();
+        let mut total_cost = U256::zero();
+        for entry in &self.0 {
+            let cost = entry.gas * entry.gas_price;
+            writeln!(
+                w,
+                "  {}: address={:#x} gas={} cost={} ETH",
+                entry.contract,
+                entry.address,
+                entry.gas,
+                ethers::utils::format_units(cost, "ether")?,
+            )?;
+            total_gas += entry.gas;
+            total_cost += cost;
+        }
+        writeln!(w, "Total: gas={total_gas} cost={} ETH", {
+            ethers::utils::format_units(total_cost, "ether")?
+        })?;
+        Ok(())
+    }
+}
+
+/// The state of a single contract's deployment, as recorded in a `--broadcast-out` artifact.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum BroadcastStatus {
+    Pending,
+    Confirmed,
+    Failed,
+}
+
+/// An entry in a [`BroadcastLog`], recording the progress of a single contract's deployment.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct BroadcastEntry {
+    status: BroadcastStatus,
+    tx_hash: Option<TxHash>,
+    address: Option<Address>,
+    block_number: Option<u64>,
+    error: Option<String>,
+    /// ABI-encoded constructor arguments used to deploy this contract, if any, so that a resumed
+    /// run can still `--verify` a contract confirmed in a previous run.
+    constructor_args: Option<Bytes>,
+}
+
+/// A JSON broadcast artifact recording the status of each contract across a deployment, so that
+/// an interrupted deployment can be resumed instead of re-run from scratch.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct BroadcastLog(HashMap<Contract, BroadcastEntry>);
+
+impl BroadcastLog {
+    fn load(path: &Path) -> anyhow::Result<Self> {
+        let file = File::open(path).with_context(|| format!("opening {}", path.display()))?;
+        serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("parsing broadcast log {}", path.display()))
+    }
+
+    fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let file = File::options()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(path)
+            .with_context(|| format!("opening {}", path.display()))?;
+        serde_json::to_writer_pretty(file, self)
+            .with_context(|| format!("writing broadcast log {}", path.display()))
+    }
+
+    fn set_pending(&mut self, contract: Contract, tx_hash: TxHash) {
+        self.0.insert(
+            contract,
+            BroadcastEntry {
+                status: BroadcastStatus::Pending,
+                tx_hash: Some(tx_hash),
+                address: None,
+                block_number: None,
+                error: None,
+                constructor_args: None,
+            },
+        );
+    }
+
+    fn set_confirmed(
+        &mut self,
+        contract: Contract,
+        tx_hash: TxHash,
+        address: Address,
+        block_number: Option<u64>,
+        constructor_args: Option<Bytes>,
+    ) {
+        self.0.insert(
+            contract,
+            BroadcastEntry {
+                status: BroadcastStatus::Confirmed,
+                tx_hash: Some(tx_hash),
+                address: Some(address),
+                block_number,
+                error: None,
+                constructor_args,
+            },
+        );
+    }
+
+    fn set_failed(&mut self, contract: Contract, tx_hash: Option<TxHash>, error: String) {
+        self.0.insert(
+            contract,
+            BroadcastEntry {
+                status: BroadcastStatus::Failed,
+                tx_hash,
+                address: None,
+                block_number: None,
+                error: Some(error),
+                constructor_args: None,
+            },
+        );
+    }
+}
+
 /// Cache of contracts predeployed or deployed during this current run.
-struct Contracts(HashMap<Contract, Address>);
+struct Contracts {
+    deployed: HashMap<Contract, Address>,
+    gas: GasOptions,
+    dry_run: bool,
+    dry_run_nonce: Option<U256>,
+    dry_run_report: DryRunReport,
+    /// ABI-encoded constructor arguments used for each deployed contract, for verification.
+    constructor_args: HashMap<Contract, Bytes>,
+    /// Contracts actually deployed by this deployer, either during this run or in an earlier run
+    /// recorded as `confirmed` in a `--broadcast-out` artifact, as opposed to predeployed
+    /// addresses supplied by the operator. Used to scope `--verify` to contracts we deployed
+    /// ourselves.
+    newly_deployed: HashSet<Contract>,
+    /// Number of confirmations to wait for before considering a deployment confirmed.
+    confirmations: usize,
+    /// Where to persist `broadcast_log`, if `--broadcast-out` was given.
+    broadcast_out: Option<PathBuf>,
+    broadcast_log: BroadcastLog,
+}
 
-impl From<DeployedContracts> for Contracts {
-    fn from(deployed: DeployedContracts) -> Self {
+impl Contracts {
+    fn new(deployed: DeployedContracts, gas: GasOptions, dry_run: bool) -> Self {
         let mut m = HashMap::new();
         if let Some(addr) = deployed.hotshot {
             m.insert(Contract::HotShot, addr);
@@ -161,7 +590,133 @@ impl From<DeployedContracts> for Contracts {
         if let Some(addr) = deployed.light_client_proxy {
             m.insert(Contract::LightClientProxy, addr);
         }
-        Self(m)
+        Self {
+            deployed: m,
+            gas,
+            dry_run,
+            dry_run_nonce: None,
+            dry_run_report: DryRunReport::default(),
+            constructor_args: HashMap::new(),
+            newly_deployed: HashSet::new(),
+            confirmations: 1,
+            broadcast_out: None,
+            broadcast_log: BroadcastLog::default(),
+        }
+    }
+
+    /// Load a `--broadcast-out` artifact from a previous run, if one is configured and exists.
+    ///
+    /// Any contract recorded there as `confirmed` is treated like a predeployed address, so that
+    /// a deployment interrupted partway through can be resumed by re-running with the same
+    /// `--broadcast-out` path.
+    fn load_broadcast_log(&mut self, opt: &BroadcastOptions) -> anyhow::Result<()> {
+        self.confirmations = opt.confirmations;
+        self.broadcast_out = opt.broadcast_out.clone();
+        let Some(path) = &self.broadcast_out else {
+            return Ok(());
+        };
+        if !path.exists() {
+            return Ok(());
+        }
+        self.broadcast_log = BroadcastLog::load(path)?;
+        for (contract, entry) in &self.broadcast_log.0 {
+            if entry.status == BroadcastStatus::Confirmed {
+                if let Some(address) = entry.address {
+                    if !self.deployed.contains_key(contract) {
+                        self.deployed.insert(*contract, address);
+                        self.newly_deployed.insert(*contract);
+                    }
+                }
+                if let Some(constructor_args) = &entry.constructor_args {
+                    self.constructor_args
+                        .entry(*contract)
+                        .or_insert_with(|| constructor_args.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Persist the current broadcast log, if `--broadcast-out` was given.
+    fn save_broadcast_log(&self) -> anyhow::Result<()> {
+        let Some(path) = &self.broadcast_out else {
+            return Ok(());
+        };
+        self.broadcast_log.save(path)
+    }
+
+    /// Get the next nonce to use when predicting a CREATE address in dry-run mode.
+    ///
+    /// The nonce is fetched from the chain once, then incremented locally for each subsequent
+    /// simulated deployment, since dry-run transactions are never actually broadcast.
+    async fn next_dry_run_nonce<M: Middleware>(&mut self, client: &M) -> anyhow::Result<U256> {
+        let nonce = match self.dry_run_nonce {
+            Some(nonce) => nonce,
+            None => {
+                let sender = client
+                    .default_sender()
+                    .context("deployer account has no default sender")?;
+                client
+                    .get_transaction_count(sender, None)
+                    .await
+                    .context("fetching deployer nonce")?
+            }
+        };
+        self.dry_run_nonce = Some(nonce + 1);
+        Ok(nonce)
+    }
+
+    /// Broadcast `tx` and wait for the configured number of confirmations, recording progress to
+    /// the broadcast log (if `--broadcast-out` was given) at each step so the deployment can be
+    /// resumed if it is interrupted.
+    ///
+    /// Returns the deployed contract's address along with the transaction receipt, so that
+    /// callers which need to inspect logs emitted by the same transaction (e.g. to confirm an
+    /// initializer ran) don't have to re-fetch it.
+    async fn broadcast<M: Middleware>(
+        &mut self,
+        name: Contract,
+        client: &M,
+        tx: TypedTransaction,
+    ) -> anyhow::Result<(Address, TransactionReceipt)> {
+        let pending = client
+            .send_transaction(tx, None)
+            .await
+            .context("broadcasting transaction")?;
+        let tx_hash: TxHash = *pending;
+        self.broadcast_log.set_pending(name, tx_hash);
+        self.save_broadcast_log()?;
+
+        let receipt = match pending.confirmations(self.confirmations).await {
+            Ok(Some(receipt)) => receipt,
+            Ok(None) => {
+                self.broadcast_log.set_failed(
+                    name,
+                    Some(tx_hash),
+                    "transaction dropped from mempool".to_string(),
+                );
+                self.save_broadcast_log()?;
+                bail!("transaction for {name} ({tx_hash:#x}) was dropped from the mempool");
+            }
+            Err(err) => {
+                self.broadcast_log
+                    .set_failed(name, Some(tx_hash), err.to_string());
+                self.save_broadcast_log()?;
+                return Err(err).context("waiting for confirmations");
+            }
+        };
+        let address = receipt
+            .contract_address
+            .context("transaction receipt is missing a deployed contract address")?;
+        self.broadcast_log.set_confirmed(
+            name,
+            tx_hash,
+            address,
+            receipt.block_number.map(|n| n.as_u64()),
+            self.constructor_args.get(&name).cloned(),
+        );
+        self.save_broadcast_log()?;
+        Ok((address, receipt))
     }
 }
 
@@ -177,7 +732,7 @@ impl Contracts {
         name: Contract,
         deploy: impl FnOnce(&mut Self) -> BoxFuture<'_, anyhow::Result<Address>>,
     ) -> anyhow::Result<Address> {
-        if let Some(addr) = self.0.get(&name) {
+        if let Some(addr) = self.deployed.get(&name) {
             tracing::info!("skipping deployment of {name}, already deployed at {addr:#x}");
             return Ok(*addr);
         }
@@ -185,26 +740,47 @@ impl Contracts {
         let addr = deploy(self).await?;
         tracing::info!("deployed {name} at {addr:#x}");
 
-        self.0.insert(name, addr);
+        self.deployed.insert(name, addr);
+        self.newly_deployed.insert(name);
         Ok(addr)
     }
 
     /// Deploy a contract by executing its deploy transaction.
     ///
-    /// The transaction will only be broadcast if contract `name` is not already deployed.
+    /// The transaction will only be broadcast if contract `name` is not already deployed. Gas
+    /// pricing is taken from the configured [`GasOptions`], falling back to a legacy or
+    /// EIP-1559 estimate as appropriate.
     async fn deploy_tx<M, C>(
         &mut self,
         name: Contract,
-        tx: ContractDeployer<M, C>,
+        mut tx: ContractDeployer<M, C>,
     ) -> anyhow::Result<Address>
     where
         M: Middleware + 'static,
         C: Deref<Target = ContractBindings<M>> + From<ContractInstance<Arc<M>, M>> + Send + 'static,
     {
-        self.deploy_fn(name, |_| {
-            async {
-                let contract = tx.send().await?;
-                Ok(contract.address())
+        self.deploy_fn(name, |contracts| {
+            async move {
+                let gas_price = contracts.gas.apply(tx.client.as_ref(), &mut tx.tx).await?;
+                if contracts.dry_run {
+                    let gas = estimate_dry_run_gas(tx.client.as_ref(), &tx.tx, name).await?;
+                    let sender = tx
+                        .client
+                        .default_sender()
+                        .context("deployer account has no default sender")?;
+                    let nonce = contracts.next_dry_run_nonce(tx.client.as_ref()).await?;
+                    let address = ethers::utils::get_contract_address(sender, nonce);
+                    contracts.dry_run_report.0.push(DryRunEntry {
+                        contract: name,
+                        address,
+                        gas,
+                        gas_price,
+                    });
+                    return Ok(address);
+                }
+                let (address, _receipt) =
+                    contracts.broadcast(name, tx.client.as_ref(), tx.tx).await?;
+                Ok(address)
             }
             .boxed()
         })
@@ -213,7 +789,7 @@ impl Contracts {
 
     /// Write a .env file.
     fn write(&self, mut w: impl Write) -> anyhow::Result<()> {
-        for (contract, address) in &self.0 {
+        for (contract, address) in &self.deployed {
             writeln!(w, "{contract}={address:#x}")?;
         }
         Ok(())
@@ -226,7 +802,6 @@ async fn main() -> anyhow::Result<()> {
     setup_backtrace();
 
     let opt = Options::parse();
-    let mut contracts = Contracts::from(opt.contracts);
 
     let provider = Provider::<Http>::try_from(opt.rpc_url.to_string())?;
     let chain_id = provider.get_chainid().await?.as_u64();
@@ -237,6 +812,20 @@ async fn main() -> anyhow::Result<()> {
         .with_chain_id(chain_id);
     let l1 = Arc::new(SignerMiddleware::new(provider, wallet));
 
+    match opt.command {
+        Command::Deploy(deploy_opt) => run_deploy(l1, deploy_opt).await,
+        Command::Upgrade(upgrade_opt) => run_upgrade(l1, upgrade_opt).await,
+    }
+}
+
+async fn run_deploy<M: Middleware + 'static>(
+    l1: Arc<M>,
+    opt: DeployOptions,
+) -> anyhow::Result<()> {
+    let mut contracts = Contracts::new(opt.contracts, opt.gas, opt.dry_run);
+    contracts.load_broadcast_log(&opt.broadcast)?;
+    let init_event_timeout = Duration::from_secs(opt.init_event_timeout_secs);
+
     contracts
         .deploy_tx(Contract::HotShot, HotShot::deploy(l1.clone(), ())?)
         .await?;
@@ -254,19 +843,201 @@ async fn main() -> anyhow::Result<()> {
                     l1.clone(),
                 );
                 let genesis = light_client_genesis(&orchestrator_url).await?;
+                tracing::info!(
+                    "initializing LightClient proxy with genesis stake table commitment {genesis:?}"
+                );
                 let data = light_client
                     .initialize(genesis.into(), u32::MAX)
                     .calldata()
                     .context("calldata for initialize transaction not available")?;
-                let proxy = ERC1967Proxy::deploy(l1, (light_client.address(), data))?
-                    .send()
+                contracts.constructor_args.insert(
+                    Contract::LightClientProxy,
+                    ethers::abi::encode(&[
+                        ethers::abi::Token::Address(light_client.address()),
+                        ethers::abi::Token::Bytes(data.to_vec()),
+                    ])
+                    .into(),
+                );
+                let watcher_client = l1.clone();
+                let mut deployer = ERC1967Proxy::deploy(l1, (light_client.address(), data))?;
+                let gas_price = contracts
+                    .gas
+                    .apply(deployer.client.as_ref(), &mut deployer.tx)
+                    .await?;
+                if contracts.dry_run {
+                    let gas = estimate_dry_run_gas(
+                        deployer.client.as_ref(),
+                        &deployer.tx,
+                        Contract::LightClientProxy,
+                    )
                     .await?;
-                Ok(proxy.address())
+                    let sender = deployer
+                        .client
+                        .default_sender()
+                        .context("deployer account has no default sender")?;
+                    let nonce = contracts
+                        .next_dry_run_nonce(deployer.client.as_ref())
+                        .await?;
+                    let address = ethers::utils::get_contract_address(sender, nonce);
+                    contracts.dry_run_report.0.push(DryRunEntry {
+                        contract: Contract::LightClientProxy,
+                        address,
+                        gas,
+                        gas_price,
+                    });
+                    return Ok(address);
+                }
+                let (address, receipt) = contracts
+                    .broadcast(Contract::LightClientProxy, deployer.client.as_ref(), deployer.tx)
+                    .await?;
+                await_initialization_event(
+                    watcher_client.as_ref(),
+                    address,
+                    receipt.block_number.map(|n| n.as_u64()),
+                    init_event_timeout,
+                )
+                .await?;
+                Ok(address)
             }
             .boxed()
         })
         .await?;
 
+    if opt.dry_run {
+        contracts.dry_run_report.print(stdout())?;
+    }
+
+    // Write out the deployed addresses before attempting verification, so that a deployment
+    // which succeeded but whose post-deployment verification failed (e.g. the explorer is down,
+    // or the API key is wrong) still leaves the operator with the addresses they paid gas for.
+    if let Some(out) = &opt.out {
+        let file = File::options().create(true).write(true).open(out)?;
+        contracts.write(file)?;
+    } else {
+        contracts.write(stdout())?;
+    }
+
+    if opt.verify.verify {
+        if contracts.dry_run {
+            tracing::warn!("skipping verification in dry-run mode, nothing was deployed");
+        } else if let Err(err) = verify::verify_all(&contracts, &opt.verify).await {
+            tracing::error!("contract verification failed: {err:#}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Confirm the LightClient's initialization event was emitted by a freshly deployed proxy.
+///
+/// `initialize` is called in the same deployment, but a revert inside `initialize` does not
+/// necessarily fail the outer transaction (e.g. if it is reached via a low-level `delegatecall`
+/// that swallows the revert), so a mined proxy deploy transaction is not on its own proof that
+/// initialization succeeded. The `Initialized` event is emitted inside that same transaction, so
+/// rather than watching for a future log (which a transaction that has already been mined and
+/// confirmed could never produce), this reads logs starting at `from_block`, the block the proxy
+/// deploy transaction was mined in. Retries for `timeout` to allow for the RPC node's log index to
+/// catch up with the block it just reported as confirmed, bailing out with a clear error if the
+/// event is never observed.
+async fn await_initialization_event<M: Middleware>(
+    client: &M,
+    proxy: Address,
+    from_block: Option<u64>,
+    timeout: Duration,
+) -> anyhow::Result<()> {
+    let event = LIGHTCLIENT_ABI
+        .event("Initialized")
+        .context("LightClient ABI has no Initialized event")?;
+    let mut filter = Filter::new().address(proxy).topic0(event.signature());
+    if let Some(from_block) = from_block {
+        filter = filter.from_block(from_block);
+    }
+
+    let poll = async {
+        loop {
+            let logs = client
+                .get_logs(&filter)
+                .await
+                .map_err(|err| anyhow::anyhow!("fetching initialization event logs: {err}"))?;
+            if let Some(log) = logs.into_iter().next() {
+                return Ok::<_, anyhow::Error>(log);
+            }
+            sleep(Duration::from_secs(2)).await;
+        }
+    };
+    let log = async_std::future::timeout(timeout, poll)
+        .await
+        .context(
+            "timed out waiting for the LightClient proxy to emit its initialization event; the \
+             deploy transaction was mined but initialization may have silently failed",
+        )??;
+
+    tracing::info!(
+        "observed LightClient proxy {proxy:#x} initialization event in tx {:?}; cross-check the \
+         genesis stake table commitment logged above against `light_client_genesis`",
+        log.transaction_hash,
+    );
+    Ok(())
+}
+
+/// Deploy a new LightClient implementation and point an existing proxy at it.
+async fn run_upgrade<M: Middleware + 'static>(
+    l1: Arc<M>,
+    opt: UpgradeOptions,
+) -> anyhow::Result<()> {
+    let proxy = LightClient::new(opt.light_client_proxy, l1.clone());
+    let owner = proxy
+        .owner()
+        .call()
+        .await
+        .context("fetching LightClient owner")?;
+    let caller = l1
+        .default_sender()
+        .context("upgrade account has no default sender")?;
+    ensure!(
+        owner == caller,
+        "caller {caller:#x} is not the owner of LightClient proxy {:#x} (owner is {owner:#x})",
+        opt.light_client_proxy,
+    );
+
+    let mut contracts = Contracts::new(
+        DeployedContracts {
+            hotshot: None,
+            plonk_verifier: opt.plonk_verifier,
+            light_client_state_update_vk: opt.light_client_state_update_vk,
+            light_client: opt.light_client,
+            light_client_proxy: None,
+        },
+        opt.gas,
+        false,
+    );
+    let new_implementation = contracts
+        .deploy_fn(Contract::LightClient, |contracts| {
+            deploy_light_client_contract(l1.clone(), contracts).boxed()
+        })
+        .await?;
+
+    let data = opt.migration_call_data.unwrap_or_default();
+    let mut call = proxy.upgrade_to_and_call(new_implementation, data);
+    contracts
+        .gas
+        .apply(call.client.as_ref(), &mut call.tx)
+        .await?;
+    ensure!(
+        call.tx.to().and_then(|to| to.as_address()) == Some(&opt.light_client_proxy),
+        "upgradeToAndCall transaction's recipient changed unexpectedly while applying gas \
+         options; aborting rather than sending the upgrade call to the wrong address",
+    );
+    call.send()
+        .await
+        .context("sending upgradeToAndCall transaction")?
+        .await
+        .context("waiting for upgradeToAndCall transaction")?;
+    tracing::info!(
+        "upgraded LightClient proxy {:#x} to implementation {new_implementation:#x}",
+        opt.light_client_proxy
+    );
+
     if let Some(out) = &opt.out {
         let file = File::options().create(true).write(true).open(out)?;
         contracts.write(file)?;
@@ -327,6 +1098,7 @@ async fn deploy_light_client_contract<M: Middleware + 'static>(
             .clone(),
         l1,
     );
-    let contract = light_client_factory.deploy(())?.send().await?;
-    Ok(contract.address())
+    contracts
+        .deploy_tx(Contract::LightClient, light_client_factory.deploy(())?)
+        .await
 }
@@ -0,0 +1,285 @@
+//! Post-deployment contract source verification against an Etherscan-compatible explorer.
+
+use super::{Contract, Contracts, VerifyOptions};
+use anyhow::{bail, Context};
+use async_std::task::sleep;
+use ethers::types::Bytes;
+use serde::Deserialize;
+use serde_json::Value;
+use std::{collections::HashMap, fs::File, io::BufReader, path::Path, time::Duration};
+use url::Url;
+
+/// Default Etherscan API endpoint, used when `--verifier-url` is not given.
+const DEFAULT_VERIFIER_URL: &str = "https://api.etherscan.io/api";
+
+/// Source file and linked-library metadata needed to verify a single contract.
+struct VerifyTarget {
+    /// Name of the Solidity source file, e.g. `HotShot.sol`, used to locate the forge artifact
+    /// under `contracts/out/<file>/<name>.json`.
+    file: &'static str,
+    /// Repo-relative path to the source file, e.g. `contracts/src/HotShot.sol`, as it appears as
+    /// a key in the compiler input/output and as Etherscan's `contractname` expects it.
+    source_path: &'static str,
+    /// Name of the contract within the source file.
+    name: &'static str,
+    /// Fully qualified library paths this contract was linked against, paired with the
+    /// [`Contract`] whose deployed address fills in that library.
+    libraries: &'static [(&'static str, Contract)],
+}
+
+/// Look up the source location and library links needed to verify `contract`.
+fn verify_target(contract: Contract) -> VerifyTarget {
+    match contract {
+        Contract::HotShot => VerifyTarget {
+            file: "HotShot.sol",
+            source_path: "contracts/src/HotShot.sol",
+            name: "HotShot",
+            libraries: &[],
+        },
+        Contract::PlonkVerifier => VerifyTarget {
+            file: "PlonkVerifier.sol",
+            source_path: "contracts/src/libraries/PlonkVerifier.sol",
+            name: "PlonkVerifier",
+            libraries: &[],
+        },
+        Contract::StateUpdateVK => VerifyTarget {
+            file: "LightClientStateUpdateVK.sol",
+            source_path: "contracts/src/libraries/LightClientStateUpdateVK.sol",
+            name: "LightClientStateUpdateVK",
+            libraries: &[],
+        },
+        Contract::LightClient => VerifyTarget {
+            file: "LightClient.sol",
+            source_path: "contracts/src/LightClient.sol",
+            name: "LightClient",
+            libraries: &[
+                (
+                    "contracts/src/libraries/PlonkVerifier.sol:PlonkVerifier",
+                    Contract::PlonkVerifier,
+                ),
+                (
+                    "contracts/src/libraries/LightClientStateUpdateVK.sol:LightClientStateUpdateVK",
+                    Contract::StateUpdateVK,
+                ),
+            ],
+        },
+        Contract::LightClientProxy => VerifyTarget {
+            file: "ERC1967Proxy.sol",
+            source_path: "lib/openzeppelin-contracts/contracts/proxy/ERC1967/ERC1967Proxy.sol",
+            name: "ERC1967Proxy",
+            libraries: &[],
+        },
+    }
+}
+
+/// The subset of a forge build artifact we need to submit a verification request.
+#[derive(Deserialize)]
+struct ForgeArtifact {
+    /// Solc metadata *output*, as produced by `forge build`. Carries the compiler version and
+    /// optimizer settings actually used, but its `sources` entries only have `keccak256`/`urls`,
+    /// not source `content`, so it cannot be submitted as Etherscan's standard-json-input.
+    metadata: Value,
+}
+
+/// The subset of a forge `build-info` artifact we need to recover the compiler's standard-json
+/// input, which `forge build` echoes back verbatim alongside the compiler output.
+#[derive(Deserialize)]
+struct BuildInfo {
+    /// The exact standard-json input solc was invoked with.
+    input: Value,
+    /// The standard-json output, used only to find which build-info file covers a given
+    /// source/contract pair.
+    output: Value,
+}
+
+/// Find the `contracts/out/build-info/*.json` file that compiled `source_path`'s `name` contract,
+/// and return the standard-json input it was compiled from.
+fn load_standard_json_input(source_path: &str, name: &str) -> anyhow::Result<Value> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../contracts/out/build-info");
+    for entry in std::fs::read_dir(&dir).with_context(|| format!("reading {}", dir.display()))? {
+        let path = entry?.path();
+        let info: BuildInfo = serde_json::from_reader(BufReader::new(File::open(&path)?))
+            .with_context(|| format!("parsing build info {}", path.display()))?;
+        if info.output["contracts"][source_path]
+            .get(name)
+            .is_some()
+        {
+            return Ok(info.input);
+        }
+    }
+    bail!("no build-info artifact in {} covers {source_path}:{name}", dir.display())
+}
+
+#[derive(Deserialize)]
+struct EtherscanResponse {
+    status: String,
+    message: String,
+    result: String,
+}
+
+/// Submit every contract in `contracts` that this deployer deployed itself (its `newly_deployed`
+/// set; operator-supplied predeployed addresses are skipped) for source verification, then poll
+/// each submission until the explorer reports success or failure.
+pub(crate) async fn verify_all(contracts: &Contracts, opt: &VerifyOptions) -> anyhow::Result<()> {
+    let api_key = opt
+        .etherscan_api_key
+        .clone()
+        .context("--etherscan-api-key is required to verify contracts")?;
+    let verifier_url = opt
+        .verifier_url
+        .clone()
+        .unwrap_or_else(|| Url::parse(DEFAULT_VERIFIER_URL).unwrap());
+    let client = reqwest::Client::new();
+
+    for &contract in &contracts.newly_deployed {
+        let address = contracts.deployed[&contract];
+        if let Err(err) =
+            verify_contract(&client, &verifier_url, &api_key, contracts, contract, address).await
+        {
+            tracing::error!("verifying {contract} failed: {err:#}");
+        }
+    }
+    Ok(())
+}
+
+async fn verify_contract(
+    client: &reqwest::Client,
+    verifier_url: &Url,
+    api_key: &str,
+    contracts: &Contracts,
+    contract: Contract,
+    address: ethers::types::Address,
+) -> anyhow::Result<()> {
+    let target = verify_target(contract);
+    let bytecode_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../contracts/out")
+        .join(target.file)
+        .join(format!("{}.json", target.name));
+    let artifact: ForgeArtifact =
+        serde_json::from_reader(BufReader::new(File::open(&bytecode_path)?))?;
+
+    let compiler_version = artifact.metadata["compiler"]["version"]
+        .as_str()
+        .context("missing compiler version in build artifact")?;
+    let optimizer = &artifact.metadata["settings"]["optimizer"];
+    let optimization_used = optimizer["enabled"].as_bool().unwrap_or(false);
+    let runs = optimizer["runs"].as_u64().unwrap_or(200);
+
+    let standard_json_input = load_standard_json_input(target.source_path, target.name)
+        .context("loading standard-json compiler input")?;
+
+    let constructor_args = contracts
+        .constructor_args
+        .get(&contract)
+        .cloned()
+        .unwrap_or_default();
+
+    let mut form: HashMap<String, String> = HashMap::from([
+        ("apikey".to_string(), api_key.to_string()),
+        ("module".to_string(), "contract".to_string()),
+        ("action".to_string(), "verifysourcecode".to_string()),
+        ("contractaddress".to_string(), format!("{address:#x}")),
+        (
+            "sourceCode".to_string(),
+            serde_json::to_string(&standard_json_input)
+                .context("serializing standard-json input")?,
+        ),
+        (
+            "codeformat".to_string(),
+            "solidity-standard-json-input".to_string(),
+        ),
+        (
+            "contractname".to_string(),
+            format!("{}:{}", target.source_path, target.name),
+        ),
+        ("compilerversion".to_string(), compiler_version.to_string()),
+        (
+            "optimizationUsed".to_string(),
+            if optimization_used { "1" } else { "0" }.to_string(),
+        ),
+        ("runs".to_string(), runs.to_string()),
+        (
+            "constructorArguements".to_string(),
+            hex_no_prefix(&constructor_args),
+        ),
+    ]);
+    for (i, (lib_path, lib_contract)) in target.libraries.iter().enumerate() {
+        let lib_address = contracts
+            .deployed
+            .get(lib_contract)
+            .with_context(|| format!("library {lib_contract} was not deployed"))?;
+        form.insert(format!("libraryname{}", i + 1), lib_path.to_string());
+        form.insert(format!("libraryaddress{}", i + 1), format!("{lib_address:#x}"));
+    }
+
+    let submission: EtherscanResponse = client
+        .post(verifier_url.clone())
+        .form(&form)
+        .send()
+        .await
+        .context("submitting verification request")?
+        .json()
+        .await
+        .context("parsing verification response")?;
+    if submission.status != "1" {
+        if is_already_verified(&submission.message) || is_already_verified(&submission.result) {
+            tracing::info!("{contract} is already verified, skipping");
+            return Ok(());
+        }
+        bail!("verification submission rejected: {}", submission.message);
+    }
+    let guid = submission.result;
+    tracing::info!("submitted {contract} for verification, guid {guid}");
+
+    poll_verification_status(client, verifier_url, api_key, &guid).await
+}
+
+/// Poll `checkverifystatus` until the explorer reports the submission as verified or failed,
+/// giving up with an error after 30 polls spaced 5 seconds apart.
+async fn poll_verification_status(
+    client: &reqwest::Client,
+    verifier_url: &Url,
+    api_key: &str,
+    guid: &str,
+) -> anyhow::Result<()> {
+    for _ in 0..30 {
+        sleep(Duration::from_secs(5)).await;
+
+        let status: EtherscanResponse = client
+            .get(verifier_url.clone())
+            .query(&[
+                ("apikey", api_key),
+                ("module", "contract"),
+                ("action", "checkverifystatus"),
+                ("guid", guid),
+            ])
+            .send()
+            .await
+            .context("polling verification status")?
+            .json()
+            .await
+            .context("parsing verification status response")?;
+
+        if status.result.contains("Pending") {
+            continue;
+        }
+        if status.status == "1" || is_already_verified(&status.result) {
+            tracing::info!("verification {guid} succeeded: {}", status.result);
+            return Ok(());
+        }
+        bail!("verification {guid} failed: {}", status.result);
+    }
+    bail!("timed out waiting for verification {guid} to complete")
+}
+
+/// Whether an Etherscan-compatible response indicates the contract was already verified.
+///
+/// This is not a failure: it means the explorer already has the source we were about to submit,
+/// so it's treated as verification having succeeded rather than as an error.
+fn is_already_verified(message: &str) -> bool {
+    message.to_lowercase().contains("already verified")
+}
+
+fn hex_no_prefix(bytes: &Bytes) -> String {
+    ethers::utils::hex::encode(bytes.as_ref())
+}